@@ -5,61 +5,258 @@ use std::sync::Arc;
 
 mod scythe_take_home {
     use std::{
+        collections::HashMap,
+        future::Future,
         io::{self, Error, Result},
-        str::FromStr,
-        sync::Arc,
+        pin::Pin,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
     };
 
-    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWrite, AsyncWriteExt, BufReader};
     use tokio::{
-        net::{TcpListener, TcpStream},
-        sync::Mutex,
+        net::{TcpListener, UnixListener},
+        sync::{broadcast, Mutex, OnceCell, OwnedSemaphorePermit, Semaphore},
+        time::Duration,
     };
 
     use tokio::fs::File;
+    use tokio::io::SeekFrom;
+    use tokio_rustls::TlsAcceptor;
     use tokio_util::{sync::CancellationToken, task::TaskTracker};
 
-    #[derive(PartialEq)]
-    enum TcpRequest {
-        Get,
-        Shutdown,
-        Quit,
+    // Default cap on the length of a query/response line. Bytes of a query line
+    // past the cap are discarded rather than buffered, so a client sending no
+    // newline can't grow memory unbounded. Responses of commands other than GET
+    // are truncated to the cap after they are produced; GET reads its file line
+    // in full.
+    const DEFAULT_MAX_LINE_LEN: usize = 8192;
+
+    // How often the tail watcher checks the file for newly appended lines.
+    const TAIL_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+    // How many appended lines a lagging `SUBSCRIBE`r can fall behind before it
+    // starts missing them, same as `tokio::sync::broadcast`'s own backlog.
+    const TAIL_CHANNEL_CAPACITY: usize = 1024;
+
+    // Default cap on how many connections are handled at once.
+    const DEFAULT_MAX_CONNECTIONS: usize = 1024;
+
+    // Keyword of the built-in line-lookup command, registered through the same
+    // pluggable registry as user commands. Its responses are exempt from
+    // `max_response_len`: truncating a GET would silently hand back a corrupted
+    // line with no `ERR` signal, a regression from the original behavior of
+    // always returning the full line.
+    const GET_COMMAND: &str = "GET";
+
+    type CommandFuture = Pin<Box<dyn Future<Output = Result<String>> + Send>>;
+
+    // A registered command handler: takes the text after the command keyword
+    // and asynchronously produces the response line (without its trailing `\n`).
+    type CommandHandler = Arc<dyn Fn(&str) -> CommandFuture + Send + Sync>;
+
+    // Outcome of reading a single line off the wire, bounded to `max_len` bytes.
+    enum ClientLine {
+        Line(String),
+        TooLong,
     }
 
-    pub struct LineServer {
-        endpoint: String,
-        filename: String,
+    // The bound listener, either a TCP socket or a Unix domain socket. The path
+    // is kept alongside the latter so the socket file can be cleaned up on shutdown.
+    enum Listener {
+        Tcp(TcpListener),
+        Unix(UnixListener, String),
     }
 
-    impl FromStr for TcpRequest {
-        type Err = ();
+    // Maps a line number (1-indexed) to the byte offset it starts at, built once
+    // up front so GET requests can seek straight to the line instead of scanning.
+    // Also records the file's size at indexing time, so the tail watcher knows
+    // where to resume reading appended bytes from.
+    struct LineIndex {
+        offsets: Vec<u64>,
+        end_offset: u64,
+    }
 
-        fn from_str(s: &str) -> core::result::Result<Self, Self::Err> {
-            match s {
-                s if s.starts_with("GET") => Ok(TcpRequest::Get),
-                "SHUTDOWN" => Ok(TcpRequest::Shutdown),
-                "QUIT" => Ok(TcpRequest::Quit),
-                _ => Err(()),
-            }
-        }
+    pub struct LineServer {
+        endpoint: String,
+        filename: String,
+        index: OnceCell<Arc<LineIndex>>,
+        tls_cert_path: Option<String>,
+        tls_key_path: Option<String>,
+        commands: std::sync::Mutex<HashMap<String, CommandHandler>>,
+        max_query_len: AtomicUsize,
+        max_response_len: AtomicUsize,
+        tail_tx: OnceCell<broadcast::Sender<String>>,
+        max_connections: AtomicUsize,
     }
 
     impl LineServer {
-        // Creates a new instance of a LineServer
-        pub fn new(endpoint: &str, filename: &str) -> LineServer {
+        // Creates a new instance of a LineServer. `tls_cert_path` and `tls_key_path`
+        // are optional; when both are set the server terminates TLS on every
+        // accepted connection instead of serving plain TCP.
+        pub fn new(
+            endpoint: &str,
+            filename: &str,
+            tls_cert_path: Option<&str>,
+            tls_key_path: Option<&str>,
+        ) -> LineServer {
             LineServer {
                 endpoint: endpoint.to_string(),
                 filename: filename.to_string(),
+                index: OnceCell::new(),
+                tls_cert_path: tls_cert_path.map(str::to_string),
+                tls_key_path: tls_key_path.map(str::to_string),
+                commands: std::sync::Mutex::new(HashMap::new()),
+                max_query_len: AtomicUsize::new(DEFAULT_MAX_LINE_LEN),
+                max_response_len: AtomicUsize::new(DEFAULT_MAX_LINE_LEN),
+                tail_tx: OnceCell::new(),
+                max_connections: AtomicUsize::new(DEFAULT_MAX_CONNECTIONS),
             }
         }
 
+        // Registers a command keyword (e.g. `"PING"`) with a handler that's given
+        // the text after the keyword and returns the response line to send back.
+        // Can be called any number of times before `run` to add commands beyond
+        // the built-in `GET`/`QUIT`/`SHUTDOWN`/`SUBSCRIBE`; registering a keyword
+        // again replaces its handler. The built-ins can't be overridden: `QUIT`,
+        // `SHUTDOWN` and `SUBSCRIBE` are matched before the registry is consulted,
+        // and `run` registers `GET` itself, replacing any earlier handler for it.
+        pub fn register<F, Fut>(&self, name: &str, handler: F)
+        where
+            F: Fn(&str) -> Fut + Send + Sync + 'static,
+            Fut: Future<Output = Result<String>> + Send + 'static,
+        {
+            let handler: CommandHandler = Arc::new(move |arg: &str| {
+                Box::pin(handler(arg)) as CommandFuture
+            });
+
+            self.commands.lock().unwrap().insert(name.to_string(), handler);
+        }
+
+        // Sets the maximum number of bytes buffered for an incoming query line
+        // before it's rejected with `ERR` instead of read in full.
+        pub fn set_max_query_len(&self, max_query_len: usize) {
+            self.max_query_len.store(max_query_len, Ordering::Relaxed);
+        }
+
+        // Sets the maximum number of bytes written back for a single response
+        // line; longer responses are truncated to this length, except those of
+        // the built-in `GET`, which always returns the full line.
+        pub fn set_max_response_len(&self, max_response_len: usize) {
+            self.max_response_len
+                .store(max_response_len, Ordering::Relaxed);
+        }
+
+        // Sets the maximum number of connections handled at once; further
+        // accepted connections wait for one of these to free up before being
+        // dispatched. Must be called before `run`.
+        pub fn set_max_connections(&self, max_connections: usize) {
+            self.max_connections
+                .store(max_connections, Ordering::Relaxed);
+        }
+
         // The function the runs the main loop that takes care of accepting incoming connections
-        // and gracefully shutting down when a `TcpRequest::Shutdown` is received by a client.
+        // and gracefully shutting down when a `SHUTDOWN` command is received by a client.
         pub async fn run(self: Arc<Self>) -> Result<()> {
+            // Build the line index once up front; every connection task shares it
+            // read-only through the `Arc` already wrapping `self`.
+            let index = Arc::new(self.build_index().await?);
+            self.index
+                .set(index.clone())
+                .map_err(|_| Error::new(io::ErrorKind::Other, "index already built"))?;
+
+            // Register the built-in GET handler now that the index exists; this
+            // goes through the same registry that `register` exposes to callers.
+            let filename = self.filename.clone();
+            self.register(GET_COMMAND, move |arg: &str| {
+                let index = index.clone();
+                let filename = filename.clone();
+                let arg = arg.to_string();
+                async move { Self::get_line(&filename, &index, &arg).await }
+            });
+
+            // Only set up once; `None` when no cert/key was configured, in which
+            // case every connection is handled as plain TCP as before.
+            let tls_acceptor = self.build_tls_acceptor()?;
+
+            // Bounds how many connections are handled at once; accepting further
+            // connections waits here for a permit to free up.
+            let connection_semaphore =
+                Arc::new(Semaphore::new(self.max_connections.load(Ordering::Relaxed)));
+
             let token = CancellationToken::new();
-            let listener = TcpListener::bind(&self.endpoint).await?;
+            let listener = self.bind_endpoint().await?;
             let tracker = TaskTracker::new();
 
+            // One tail watcher feeds every `SUBSCRIBE`d connection through a
+            // broadcast channel, resuming from the byte offset the index ended at.
+            let (tail_tx, _) = broadcast::channel(TAIL_CHANNEL_CAPACITY);
+            self.tail_tx
+                .set(tail_tx)
+                .map_err(|_| Error::new(io::ErrorKind::Other, "tail channel already built"))?;
+
+            let watcher_self = self.clone();
+            let watcher_token = token.clone();
+            tracker.spawn(async move {
+                watcher_self.watch_for_appends(watcher_token).await;
+            });
+
+            match listener {
+                Listener::Tcp(listener) => {
+                    self.accept_loop(
+                        || async { listener.accept().await.map(|(stream, _)| stream) },
+                        tls_acceptor.clone(),
+                        &connection_semaphore,
+                        &token,
+                        &tracker,
+                    )
+                    .await;
+                }
+                Listener::Unix(listener, path) => {
+                    // TLS termination isn't meaningful over a local Unix domain
+                    // socket, so this is always plain.
+                    self.accept_loop(
+                        || async { listener.accept().await.map(|(stream, _)| stream) },
+                        None,
+                        &connection_semaphore,
+                        &token,
+                        &tracker,
+                    )
+                    .await;
+
+                    drop(listener);
+                    let _ = std::fs::remove_file(&path);
+                }
+            }
+
+            println!("Waiting for all tasks to stop..");
+
+            tracker.wait().await;
+
+            println!("All tasks have stopped, have a nice day!");
+
+            Ok(())
+        }
+
+        // Drives the accept loop shared by every listener kind: awaits the next
+        // stream from `accept`, acquires a connection permit, and spawns it,
+        // stopping once the server is cancelled. `accept` abstracts over
+        // `TcpListener::accept`/`UnixListener::accept` so only the per-kind
+        // bind and cleanup need to live outside this loop.
+        async fn accept_loop<S, F, Fut>(
+            self: &Arc<Self>,
+            mut accept: F,
+            tls_acceptor: Option<TlsAcceptor>,
+            connection_semaphore: &Arc<Semaphore>,
+            token: &CancellationToken,
+            tracker: &TaskTracker,
+        ) where
+            S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+            F: FnMut() -> Fut,
+            Fut: Future<Output = io::Result<S>>,
+        {
             loop {
                 tokio::select! {
                     _ = token.cancelled() => {
@@ -67,137 +264,533 @@ mod scythe_take_home {
                         tracker.close();
                         break;
                     }
-                    Ok((stream, _)) = listener.accept() => {
+                    Ok(stream) = accept() => {
                         println!("Connection established!");
-                        let shared_stream = Arc::new(Mutex::new(stream));
-                        let cloned_self = self.clone();
-
-                        // Each task thats spawned should pay attention to the cancellation token.
-                        // Lets clone it and shutdown the stream when the token is cancelled by
-                        // another task.
-                        let cloned_token = token.clone();
-
-                        tracker.spawn(async move {
-                            tokio::select! {
-                                _ = cloned_token.cancelled() => {
-                                    let cloned_stream = Arc::clone(&shared_stream);
-                                    let mut acquire_stream = cloned_stream.lock().await;
-                                    let _ = acquire_stream.shutdown().await;
-                                },
-                                _ = cloned_self.handle_connection(Arc::clone(&shared_stream), &cloned_token) => ()
+                        let Ok(permit) = connection_semaphore.clone().acquire_owned().await else {
+                            break;
+                        };
+                        self.spawn_connection(stream, tls_acceptor.clone(), token, tracker, permit);
+                    }
+                }
+            }
+        }
+
+        // This function takes care of handling a single client connection by reading
+        // bounded lines off the wire and dispatching the leading keyword to the
+        // matching registered command handler. Generic over the underlying stream
+        // so the same logic drives a plain `TcpStream` or a TLS-wrapped one.
+        async fn handle_connection<S>(
+            &self,
+            stream: Arc<Mutex<S>>,
+            token: &CancellationToken,
+        ) -> Result<()>
+        where
+            S: AsyncRead + AsyncWrite + Unpin,
+        {
+            let mut acquire_stream = stream.lock().await;
+            let (mut read_half, mut write_half) = tokio::io::split(&mut *acquire_stream);
+            let mut reader = BufReader::new(&mut read_half);
+            let max_query_len = self.max_query_len.load(Ordering::Relaxed);
+            let max_response_len = self.max_response_len.load(Ordering::Relaxed);
+
+            loop {
+                let client_line = match Self::read_bounded_line(&mut reader, max_query_len).await? {
+                    Some(client_line) => client_line,
+                    None => break,
+                };
+
+                let trimmed_line = match client_line {
+                    ClientLine::Line(line) => line,
+                    ClientLine::TooLong => {
+                        write_half.write_all(b"ERR\n").await?;
+                        continue;
+                    }
+                };
+
+                let (command, argument) = match trimmed_line.split_once(char::is_whitespace) {
+                    Some((command, argument)) => (command, argument.trim_start()),
+                    None => (trimmed_line.as_str(), ""),
+                };
+
+                match command {
+                    "" => continue,
+                    "QUIT" => break,
+                    "SHUTDOWN" => {
+                        // Let other tasks know that the server needs to shutdown
+                        println!("Server is shutting down..");
+                        token.cancel();
+                        break;
+                    }
+                    "SUBSCRIBE" => {
+                        // From here the connection is a one-way push stream of
+                        // appended lines, so we stop reading further commands.
+                        self.handle_subscribe(&mut write_half, token).await?;
+                        break;
+                    }
+                    _ => {
+                        let handler = self.commands.lock().unwrap().get(command).cloned();
+
+                        match handler {
+                            Some(handler) => {
+                                let mut response_line = match handler(argument).await {
+                                    Ok(response) => response,
+                                    Err(err) => {
+                                        eprintln!(
+                                            "Error while handling \'{command}\' request: {err}"
+                                        );
+                                        "ERR".to_string()
+                                    }
+                                };
+                                // The built-in GET is exempt: it always returns
+                                // the full line, matching the original behavior.
+                                // Only opt-in custom commands get truncated.
+                                if command != GET_COMMAND && response_line.len() > max_response_len {
+                                    let mut cut = max_response_len;
+                                    while cut > 0 && !response_line.is_char_boundary(cut) {
+                                        cut -= 1;
+                                    }
+                                    response_line.truncate(cut);
+                                }
+                                response_line.push('\n');
+
+                                if let Err(err) =
+                                    write_half.write_all(response_line.as_bytes()).await
+                                {
+                                    eprintln!("Error while writing to the stream: {err}");
+                                }
                             }
-                        });
+                            None => eprintln!("Invalid string found \'{trimmed_line}\'"),
+                        }
                     }
                 }
             }
 
-            println!("Waiting for all tasks to stop..");
+            Ok(())
+        }
 
-            tracker.wait().await;
+        // Reads a single line (up to and including `\n`, or EOF) from `reader`,
+        // capping memory use at `max_len` bytes regardless of how long the
+        // incoming line actually is. Returns `Ok(None)` once the connection is
+        // closed with no further data.
+        async fn read_bounded_line<R>(reader: &mut R, max_len: usize) -> Result<Option<ClientLine>>
+        where
+            R: AsyncRead + Unpin,
+        {
+            let mut buf: Vec<u8> = Vec::new();
+            let mut too_long = false;
 
-            println!("All tasks have stopped, have a nice day!");
+            loop {
+                let byte = match reader.read_u8().await {
+                    Ok(byte) => byte,
+                    Err(err)
+                        if err.kind() == io::ErrorKind::UnexpectedEof
+                            && buf.is_empty()
+                            && !too_long =>
+                    {
+                        return Ok(None)
+                    }
+                    Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => break,
+                    Err(err) => return Err(err),
+                };
 
-            Ok(())
+                if byte == b'\n' {
+                    break;
+                }
+
+                if buf.len() < max_len {
+                    buf.push(byte);
+                } else {
+                    too_long = true;
+                }
+            }
+
+            if too_long {
+                return Ok(Some(ClientLine::TooLong));
+            }
+
+            Ok(Some(ClientLine::Line(
+                String::from_utf8_lossy(&buf).trim().to_string(),
+            )))
         }
 
-        // This function takes care of handling a single client connection by parsing
-        // through the `TcpRequest`s that are sent to the server.
-        async fn handle_connection(
-            &self,
-            stream: Arc<Mutex<TcpStream>>,
-            token: &CancellationToken,
-        ) -> Result<()> {
-            let mut acquire_stream = stream.lock().await;
-            let (mut read_half, mut write_half) = tokio::io::split(&mut *acquire_stream);
-            let reader = BufReader::new(&mut read_half);
-            let mut lines = reader.lines();
+        // Reads a specific file line number from `filename`, seeking straight to
+        // its byte offset in `index` instead of scanning from the start.
+        async fn get_line(filename: &str, index: &LineIndex, arg: &str) -> Result<String> {
+            let line_number = arg.trim().parse::<usize>().map_err(|err| {
+                Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("error parsing line number \'{arg}\': {err}"),
+                )
+            })?;
+
+            if line_number == 0 || line_number > index.offsets.len() {
+                return Err(Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "line number \'{line_number}\' is out of the range \'1 to {}\'",
+                        index.offsets.len()
+                    ),
+                ));
+            }
 
-            while let Some(line) = lines.next_line().await? {
-                let trimmed_line = line.trim();
+            let mut file = File::open(filename).await?;
+            file.seek(SeekFrom::Start(index.offsets[line_number - 1]))
+                .await?;
 
-                if let Ok(request) = TcpRequest::from_str(trimmed_line) {
-                    match request {
-                        TcpRequest::Get => {
-                            self.handle_get_request(&mut write_half, trimmed_line)
-                                .await?
-                        }
-                        TcpRequest::Quit | TcpRequest::Shutdown => {
-                            if request == TcpRequest::Shutdown {
-                                // Let other tasks know that the server needs to shutdown
-                                println!("Server is shutting down..");
-                                token.cancel();
+            let mut reader = BufReader::new(file);
+            let mut line = String::new();
+            reader.read_line(&mut line).await?;
+
+            if line.ends_with('\n') {
+                line.pop();
+                if line.ends_with('\r') {
+                    line.pop();
+                }
+            }
+
+            Ok(line)
+        }
+
+        // Drives a subscribed connection: forwards every line the tail watcher
+        // broadcasts until the client disconnects or the server shuts down.
+        async fn handle_subscribe<W>(&self, write_half: &mut W, token: &CancellationToken) -> Result<()>
+        where
+            W: AsyncWrite + Unpin,
+        {
+            let mut rx = self
+                .tail_tx
+                .get()
+                .expect("tail channel should be initialized before serving SUBSCRIBE")
+                .subscribe();
+
+            loop {
+                tokio::select! {
+                    _ = token.cancelled() => break,
+                    received = rx.recv() => {
+                        match received {
+                            Ok(mut line) => {
+                                line.push('\n');
+                                if let Err(err) = write_half.write_all(line.as_bytes()).await {
+                                    eprintln!("Error while writing to the stream: {err}");
+                                    break;
+                                }
                             }
-                            break;
+                            // We fell behind the watcher's backlog and some lines
+                            // were dropped from under us. Rather than serving a
+                            // gappy stream (or dropping the client), resync: take
+                            // a fresh subscription, which only yields lines
+                            // broadcast from this point on, i.e. from the file's
+                            // current end.
+                            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                                eprintln!("Subscriber lagged behind, skipped {skipped} lines; resyncing to current file end");
+                                rx = self
+                                    .tail_tx
+                                    .get()
+                                    .expect("tail channel should be initialized before serving SUBSCRIBE")
+                                    .subscribe();
+                            }
+                            Err(broadcast::error::RecvError::Closed) => break,
                         }
                     }
-                } else {
-                    eprintln!("Invalid string found \'{line}\'");
                 }
             }
 
             Ok(())
         }
 
-        // This function handles a `GET` request as defined in the take home.
-        // NTS: Should also be an async function if we don't want to block on reading
-        // from a file and writing back to the client.
-        async fn handle_get_request(
-            &self,
-            write_stream: &mut tokio::io::WriteHalf<&mut tokio::net::TcpStream>,
-            get_str: &str,
-        ) -> Result<()> {
-            let no_get_str = get_str.replace("GET ", "");
-            let parse_num = no_get_str.trim().parse::<usize>();
-            let mut write_line = "ERR\n".to_string();
-
-            // Check if we parsed the string as usize
-            match parse_num {
-                Ok(file_line_num) => {
-                    // Read a single line from the file
-                    match self.read_line_from_file(file_line_num).await {
-                        Ok(line) => {
-                            write_line = line + "\n";
-                        }
-                        Err(err) => {
-                            eprintln!("Error while reading line number \'{file_line_num}\': {err}")
+        // Background task that watches `filename` for appended lines and
+        // broadcasts each one to every subscribed connection, resuming from the
+        // byte offset the startup index ended at.
+        async fn watch_for_appends(self: Arc<Self>, token: CancellationToken) {
+            let mut last_offset = self
+                .index
+                .get()
+                .expect("index should be built before the tail watcher starts")
+                .end_offset;
+            let mut interval = tokio::time::interval(TAIL_POLL_INTERVAL);
+
+            loop {
+                tokio::select! {
+                    _ = token.cancelled() => break,
+                    _ = interval.tick() => {
+                        match self.poll_appended_lines(last_offset).await {
+                            Ok((new_offset, lines)) => {
+                                last_offset = new_offset;
+                                for line in lines {
+                                    // Ignore send errors; they just mean no one is subscribed right now.
+                                    let _ = self.tail_tx.get().expect("tail channel should be initialized").send(line);
+                                }
+                            }
+                            Err(err) => eprintln!("Error while tailing \'{}\': {err}", self.filename),
                         }
                     }
                 }
-                Err(err) => eprintln!("Error while parsing the string \'{no_get_str}\': {err}"),
             }
+        }
 
-            // Write data back to the client
-            if let Err(err) = write_stream.write_all(write_line.as_bytes()).await {
-                eprintln!("Error while writing to the stream: {err}");
+        // Reads whatever has been appended to `filename` since `last_offset`,
+        // returning the complete lines found and the offset reached. Bytes after
+        // the last newline are left for the next poll since the line isn't done yet.
+        async fn poll_appended_lines(&self, last_offset: u64) -> Result<(u64, Vec<String>)> {
+            let mut file = File::open(&self.filename).await?;
+            let len = file.metadata().await?.len();
+
+            if len <= last_offset {
+                return Ok((last_offset, Vec::new()));
             }
 
-            Ok(())
+            file.seek(SeekFrom::Start(last_offset)).await?;
+            let mut buf = Vec::new();
+            file.read_to_end(&mut buf).await?;
+
+            let mut lines = Vec::new();
+            let mut consumed: u64 = 0;
+            let mut start = 0usize;
+
+            for (i, &byte) in buf.iter().enumerate() {
+                if byte == b'\n' {
+                    let mut line = String::from_utf8_lossy(&buf[start..i]).into_owned();
+                    if line.ends_with('\r') {
+                        line.pop();
+                    }
+                    lines.push(line);
+                    start = i + 1;
+                    consumed = start as u64;
+                }
+            }
+
+            Ok((last_offset + consumed, lines))
         }
 
-        // This function asynchronously reads a specific file line number from the file `filename`.
-        // NTS: This should be an async function to avoid blocking on reading from the file.
-        async fn read_line_from_file(&self, line_number: usize) -> Result<String> {
+        // Reads the file sequentially once, recording the starting byte offset of
+        // every line so GET requests can later seek directly to them.
+        async fn build_index(&self) -> Result<LineIndex> {
             let file = File::open(&self.filename).await?;
-            let reader = BufReader::new(file);
-            let mut lines = reader.lines();
-            let mut index = 0;
-
-            while let Some(line) = lines.next_line().await? {
-                // Return the line number (ex. 'GET 2' should return index 1)
-                if index + 1 == line_number {
-                    return Ok(line);
+            let mut reader = BufReader::new(file);
+            let mut offsets = Vec::new();
+            let mut offset: u64 = 0;
+            let mut buf = Vec::new();
+
+            loop {
+                offsets.push(offset);
+                buf.clear();
+                let bytes_read = reader.read_until(b'\n', &mut buf).await?;
+
+                if bytes_read == 0 {
+                    // We were already at EOF, so the offset we just pushed doesn't
+                    // start a real line.
+                    offsets.pop();
+                    break;
+                }
+
+                offset += bytes_read as u64;
+            }
+
+            Ok(LineIndex {
+                offsets,
+                end_offset: offset,
+            })
+        }
+
+        // Builds a TLS acceptor from the configured cert/key PEM files. Returns
+        // `None` when TLS isn't configured, so callers fall back to plain TCP.
+        fn build_tls_acceptor(&self) -> Result<Option<TlsAcceptor>> {
+            let (cert_path, key_path) = match (&self.tls_cert_path, &self.tls_key_path) {
+                (Some(cert_path), Some(key_path)) => (cert_path, key_path),
+                _ => return Ok(None),
+            };
+
+            let certs = load_certs(cert_path)?;
+            let key = load_private_key(key_path)?;
+
+            let config = tokio_rustls::rustls::ServerConfig::builder()
+                .with_no_client_auth()
+                .with_single_cert(certs, key)
+                .map_err(|err| Error::new(io::ErrorKind::InvalidData, err))?;
+
+            Ok(Some(TlsAcceptor::from(Arc::new(config))))
+        }
+
+        // Binds `endpoint` as a `unix:/path/to.sock` Unix domain socket when it
+        // carries that scheme, otherwise as a plain TCP address.
+        async fn bind_endpoint(&self) -> Result<Listener> {
+            match self.endpoint.strip_prefix("unix:") {
+                Some(path) => {
+                    // Remove a stale socket file left behind by a previous run,
+                    // otherwise binding fails with "address already in use".
+                    let _ = std::fs::remove_file(path);
+                    let listener = UnixListener::bind(path)?;
+                    Ok(Listener::Unix(listener, path.to_string()))
+                }
+                None => {
+                    let listener = TcpListener::bind(&self.endpoint).await?;
+                    Ok(Listener::Tcp(listener))
+                }
+            }
+        }
+
+        // Wraps a freshly accepted stream in TLS (if configured) and spawns the
+        // task that drives it through `handle_connection`, shutting it down if
+        // the server is cancelled first. Generic over the stream type so the
+        // same logic serves TCP, TLS-wrapped TCP, and Unix domain sockets alike.
+        // `permit` is held for the spawned task's whole lifetime and is released
+        // (freeing a `max_connections` slot) whichever way the task ends.
+        fn spawn_connection<S>(
+            self: &Arc<Self>,
+            stream: S,
+            tls_acceptor: Option<TlsAcceptor>,
+            token: &CancellationToken,
+            tracker: &TaskTracker,
+            permit: OwnedSemaphorePermit,
+        ) where
+            S: AsyncRead + AsyncWrite + Unpin + Send + 'static,
+        {
+            let cloned_self = self.clone();
+            let cloned_token = token.clone();
+
+            match tls_acceptor {
+                Some(acceptor) => {
+                    tracker.spawn(async move {
+                        let _permit = permit;
+                        let tls_stream = match acceptor.accept(stream).await {
+                            Ok(tls_stream) => tls_stream,
+                            Err(err) => {
+                                eprintln!("TLS handshake failed: {err}");
+                                return;
+                            }
+                        };
+                        let shared_stream = Arc::new(Mutex::new(tls_stream));
+
+                        tokio::select! {
+                            _ = cloned_token.cancelled() => {
+                                let mut acquire_stream = shared_stream.lock().await;
+                                let _ = acquire_stream.shutdown().await;
+                            },
+                            _ = cloned_self.handle_connection(Arc::clone(&shared_stream), &cloned_token) => ()
+                        }
+                    });
+                }
+                None => {
+                    let shared_stream = Arc::new(Mutex::new(stream));
+
+                    tracker.spawn(async move {
+                        let _permit = permit;
+                        tokio::select! {
+                            _ = cloned_token.cancelled() => {
+                                let mut acquire_stream = shared_stream.lock().await;
+                                let _ = acquire_stream.shutdown().await;
+                            },
+                            _ = cloned_self.handle_connection(Arc::clone(&shared_stream), &cloned_token) => ()
+                        }
+                    });
                 }
-                index += 1;
             }
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
 
-            Err(Error::new(
-                io::ErrorKind::InvalidInput,
-                format!(
-                    "line number \'{line_number}\' is out of the range \'1 to {}\'",
-                    index + 1
-                ),
+        // Builds a path under the system temp dir unique to this test run and
+        // process, so parallel `cargo test` runs don't collide on the same file.
+        fn temp_file_path(test_name: &str) -> std::path::PathBuf {
+            std::env::temp_dir().join(format!(
+                "line_server_test_{}_{test_name}.txt",
+                std::process::id()
             ))
         }
+
+        #[tokio::test]
+        async fn build_index_handles_empty_file() {
+            let path = temp_file_path("build_index_empty_file");
+            std::fs::write(&path, "").unwrap();
+
+            let server = LineServer::new("127.0.0.1:0", path.to_str().unwrap(), None, None);
+            let index = server.build_index().await.unwrap();
+
+            assert!(index.offsets.is_empty());
+            assert_eq!(index.end_offset, 0);
+
+            std::fs::remove_file(&path).unwrap();
+        }
+
+        #[tokio::test]
+        async fn build_index_handles_missing_trailing_newline() {
+            let path = temp_file_path("build_index_missing_trailing_newline");
+            std::fs::write(&path, "a\nbb\nccc").unwrap();
+
+            let server = LineServer::new("127.0.0.1:0", path.to_str().unwrap(), None, None);
+            let index = server.build_index().await.unwrap();
+
+            assert_eq!(index.offsets, vec![0, 2, 5]);
+            assert_eq!(index.end_offset, 8);
+
+            std::fs::remove_file(&path).unwrap();
+        }
+
+        #[tokio::test]
+        async fn get_line_rejects_out_of_range_line_numbers() {
+            let path = temp_file_path("get_line_out_of_range");
+            std::fs::write(&path, "a\nb\n").unwrap();
+            let index = LineIndex {
+                offsets: vec![0, 2],
+                end_offset: 4,
+            };
+
+            let too_high = LineServer::get_line(path.to_str().unwrap(), &index, "3")
+                .await
+                .unwrap_err();
+            assert_eq!(too_high.kind(), io::ErrorKind::InvalidInput);
+
+            let zero = LineServer::get_line(path.to_str().unwrap(), &index, "0")
+                .await
+                .unwrap_err();
+            assert_eq!(zero.kind(), io::ErrorKind::InvalidInput);
+
+            std::fs::remove_file(&path).unwrap();
+        }
+
+        #[tokio::test]
+        async fn read_bounded_line_rejects_overlong_line() {
+            let mut reader: &[u8] = b"abcdefghij";
+
+            let line = LineServer::read_bounded_line(&mut reader, 4)
+                .await
+                .unwrap();
+
+            assert!(matches!(line, Some(ClientLine::TooLong)));
+        }
+
+        #[tokio::test]
+        async fn read_bounded_line_reads_line_within_limit() {
+            let mut reader: &[u8] = b"hello\n";
+
+            let line = LineServer::read_bounded_line(&mut reader, 10)
+                .await
+                .unwrap();
+
+            assert!(matches!(line, Some(ClientLine::Line(ref s)) if s == "hello"));
+        }
+    }
+
+    // Reads a PEM file of one or more certificates, as required by `rustls`.
+    fn load_certs(
+        path: &str,
+    ) -> Result<Vec<tokio_rustls::rustls::pki_types::CertificateDer<'static>>> {
+        let file = std::fs::File::open(path)?;
+        let mut reader = std::io::BufReader::new(file);
+        rustls_pemfile::certs(&mut reader).collect()
+    }
+
+    // Reads the first private key out of a PEM file, as required by `rustls`.
+    fn load_private_key(
+        path: &str,
+    ) -> Result<tokio_rustls::rustls::pki_types::PrivateKeyDer<'static>> {
+        let file = std::fs::File::open(path)?;
+        let mut reader = std::io::BufReader::new(file);
+        rustls_pemfile::private_key(&mut reader)?
+            .ok_or_else(|| Error::new(io::ErrorKind::InvalidData, "no private key found in PEM file"))
     }
 }
 
@@ -218,8 +811,9 @@ async fn main() -> Result<()> {
     let endpoint = &args[1];
     let filename = &args[2];
 
-    // Create a new instance of a LineServer
-    let server = Arc::new(LineServer::new(endpoint, filename));
+    // Create a new instance of a LineServer. TLS isn't exposed as a CLI option,
+    // so a server built here never terminates TLS.
+    let server = Arc::new(LineServer::new(endpoint, filename, None, None));
 
     // Run the LineServer
     server.run().await?;